@@ -0,0 +1,108 @@
+//! Read-only HTTP API exposing the bot's live state as JSON, so operators
+//! have an introspection surface instead of only `tracing` output.
+//!
+//! - `GET /targets` — the current live target markets.
+//! - `GET /bets` — recently placed bets, from the storage layer.
+//! - `GET /status` — the last fetched GitHub status and whether it's healthy.
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use serde::Serialize;
+use tokio::sync::Mutex;
+use tracing::{error, info};
+use warp::Filter;
+
+use crate::github_status::StatusEnvelope;
+use crate::manifold_markets::TargetMarkets;
+use crate::shutdown::ShutdownReceiver;
+use crate::storage::Storage;
+
+/// State shared with the scan/update loops, read (never written) by the API.
+#[derive(Clone)]
+pub struct ApiState {
+    pub targets: Arc<Mutex<TargetMarkets>>,
+    pub storage: Storage,
+    pub last_status: Arc<Mutex<Option<StatusEnvelope>>>,
+}
+
+#[derive(Serialize)]
+struct StatusView {
+    indicator: Option<String>,
+    description: Option<String>,
+    is_healthy: bool,
+}
+
+/// Serve `/targets`, `/bets` and `/status` on `bind_addr`, until `shutdown` fires.
+pub async fn serve(
+    state: ApiState,
+    bind_addr: SocketAddr,
+    mut shutdown: ShutdownReceiver,
+) -> Result<()> {
+    let with_state = warp::any().map(move || state.clone());
+
+    let targets_route = warp::path("targets")
+        .and(warp::get())
+        .and(with_state.clone())
+        .and_then(targets_handler);
+
+    let bets_route = warp::path("bets")
+        .and(warp::get())
+        .and(with_state.clone())
+        .and_then(bets_handler);
+
+    let status_route = warp::path("status")
+        .and(warp::get())
+        .and(with_state)
+        .and_then(status_handler);
+
+    let routes = targets_route.or(bets_route).or(status_route);
+
+    info!(%bind_addr, "serving read-only introspection api");
+
+    let (_, server) = warp::serve(routes).bind_with_graceful_shutdown(bind_addr, async move {
+        shutdown.recv().await.ok();
+    });
+    server.await;
+
+    Ok(())
+}
+
+async fn targets_handler(state: ApiState) -> Result<impl warp::Reply, std::convert::Infallible> {
+    let targets = state.targets.lock().await.snapshot();
+    Ok(warp::reply::json(&targets))
+}
+
+async fn bets_handler(state: ApiState) -> Result<impl warp::Reply, std::convert::Infallible> {
+    let bets = state.storage.recent_bets().await.unwrap_or_else(|err| {
+        error!(%err, "failed to fetch recent bets");
+        Vec::new()
+    });
+    Ok(warp::reply::json(&bets))
+}
+
+async fn status_handler(state: ApiState) -> Result<impl warp::Reply, std::convert::Infallible> {
+    let last_status = state.last_status.lock().await;
+    let view = match last_status.as_ref() {
+        Some(status) => StatusView {
+            indicator: Some(status.indicator().to_string()),
+            description: Some(status.description().to_string()),
+            is_healthy: status.is_ok(),
+        },
+        None => StatusView {
+            indicator: None,
+            description: None,
+            is_healthy: true,
+        },
+    };
+    Ok(warp::reply::json(&view))
+}
+
+/// Read `API_BIND_ADDR` from the environment, defaulting to `0.0.0.0:8787`.
+pub fn bind_addr_from_env() -> Result<SocketAddr> {
+    std::env::var("API_BIND_ADDR")
+        .unwrap_or_else(|_| "0.0.0.0:8787".to_string())
+        .parse()
+        .context("invalid API_BIND_ADDR")
+}
@@ -0,0 +1,40 @@
+//! The bot's event bus.
+//!
+//! The moments the bot observes a GitHub incident and the moments it places
+//! (or fails to place) a bet are published here as a typed [`BotEvent`] over
+//! a `broadcast` channel, decoupling the betting logic in `scan_targets` from
+//! side-channel consumers like [`crate::notification`].
+
+use tokio::sync::broadcast;
+
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Something the bot did or observed, published for any subscriber to react to.
+#[derive(Debug, Clone)]
+pub enum BotEvent {
+    /// A GitHub incident was observed.
+    IncidentDetected {
+        indicator: String,
+        description: String,
+    },
+    /// Bets were placed on a target market; `amount` is their total.
+    BetPlaced {
+        contract_id: String,
+        amount: u32,
+    },
+    /// A batch of bets that included this target market failed to place.
+    BetFailed {
+        contract_id: String,
+        error: String,
+    },
+}
+
+pub type EventSender = broadcast::Sender<BotEvent>;
+pub type EventReceiver = broadcast::Receiver<BotEvent>;
+
+/// Create the bus's sender, kept alive for the process lifetime. Subscribers
+/// get a receiver with `sender.subscribe()`.
+pub fn new_bus() -> EventSender {
+    let (tx, _rx) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+    tx
+}
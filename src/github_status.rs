@@ -1,15 +1,19 @@
+use std::time::Instant;
+
 use anyhow::{Context, Result};
 use backoff::{future::retry, ExponentialBackoff};
 use reqwest::{self, Client, StatusCode};
 use serde::Deserialize;
 
-#[derive(Debug, Deserialize)]
+use crate::metrics::Metrics;
+
+#[derive(Debug, Clone, Deserialize)]
 struct Status {
     description: String,
     indicator: String,
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Clone, serde::Deserialize)]
 pub struct StatusEnvelope {
     status: Status,
 }
@@ -32,9 +36,15 @@ const GITHUB_STATUS_URL: &str = "https://www.githubstatus.com/api/v2/status.json
 
 /// Get the current GitHub incident status.
 /// Uses an exponential backoff on 429s, errors out on anything else.
-pub async fn get_incident_status(github_client: &Client) -> Result<StatusEnvelope> {
+pub async fn get_incident_status(
+    github_client: &Client,
+    metrics: &Metrics,
+) -> Result<StatusEnvelope> {
     use backoff::Error;
 
+    metrics.github_polls_total.increment();
+    let started_at = Instant::now();
+
     let get_status_with_backoff = || async {
         github_client
             .get(GITHUB_STATUS_URL)
@@ -44,6 +54,7 @@ pub async fn get_incident_status(github_client: &Client) -> Result<StatusEnvelop
             .error_for_status()
             .map_err(|err| {
                 if err.status() == Some(StatusCode::TOO_MANY_REQUESTS) {
+                    metrics.github_poll_retries_total.increment();
                     Error::Transient {
                         err,
                         retry_after: None,
@@ -57,7 +68,13 @@ pub async fn get_incident_status(github_client: &Client) -> Result<StatusEnvelop
             .map_err(Error::Permanent)
     };
 
-    retry(ExponentialBackoff::default(), get_status_with_backoff)
+    let result = retry(ExponentialBackoff::default(), get_status_with_backoff)
         .await
-        .context("failed to get GitHub status")
+        .context("failed to get GitHub status");
+
+    metrics
+        .github_poll_latency_ms
+        .set(started_at.elapsed().as_millis() as u64);
+
+    result
 }
@@ -5,25 +5,43 @@
 //! - [ ] Periodically check if we have bet yes on a target market. If so, add the market to your
 //! exclusion list. https://docs.manifold.markets/api#get-v0marketmarketidpositions
 //! - [ ] When betting on a target market, add the market to the exclusion list when finished.
+mod api;
+mod events;
 mod github_status;
 mod log;
 mod manifold_markets;
-
-use std::{collections::HashSet, sync::Arc, time::Duration};
+mod metrics;
+mod notification;
+mod schedule;
+mod shutdown;
+mod storage;
+
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::Duration,
+};
 
 use anyhow::Result;
-use chrono::{Datelike, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use futures::future::try_join_all;
 use lazy_static::lazy_static;
-use manifold_markets::{IncidentType, ManifoldClient, TargetMarkets};
+use manifold_markets::{BetSizing, IncidentType, ManifoldClient, TargetMarkets};
 use reqwest::{self, Client};
+use serde::Serialize;
 use tokio::{select, sync::Mutex, time::sleep};
 use tracing::{debug, info};
 
+use crate::api::ApiState;
+use crate::events::{BotEvent, EventSender};
+use crate::github_status::StatusEnvelope;
 use crate::manifold_markets::Outcome;
+use crate::metrics::Metrics;
+use crate::notification::NotificationConfig;
+use crate::shutdown::ShutdownReceiver;
+use crate::storage::{BetRecord, Storage, StorageConfig};
 
 const GITHUB_POLL_INTERVAL_MS: u64 = 500;
-const DEFAULT_BET_SIZE: u32 = 500;
 const NR_OF_BETS: u32 = 2;
 const EXCLUSION_DAY_SLEEP_MINUTES: u64 = 20;
 
@@ -31,22 +49,21 @@ lazy_static! {
     static ref DATE_EXCLUSION_LIST: [NaiveDate; 1] = [NaiveDate::from_ymd_opt(2023, 9, 6).unwrap()];
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct TargetIndicident {
-    contract_id: String,
-    day: u32,
-    incident_type: IncidentType,
-    month: u32,
+    pub contract_id: String,
+    /// The market's fully-qualified close deadline, per [`crate::schedule`].
+    pub deadline: DateTime<Utc>,
+    pub incident_type: IncidentType,
 }
 
 impl TargetIndicident {
-    fn is_past(&self) -> bool {
-        let today = Utc::now();
-        today.month() > self.month || (today.month() == self.month && today.day() > self.day)
+    pub fn is_past(&self) -> bool {
+        Utc::now() >= self.deadline
     }
 
-    fn matches(&self, now: &NaiveDate, incident_type: &IncidentType) -> bool {
-        self.month == now.month() && self.day == now.day() && self.incident_type == *incident_type
+    pub fn matches(&self, now: &NaiveDate, incident_type: &IncidentType) -> bool {
+        self.deadline.date_naive() == *now && self.incident_type == *incident_type
     }
 }
 
@@ -54,10 +71,26 @@ async fn scan_targets(
     github_client: &Client,
     manifold_client: &ManifoldClient,
     target_markets: Arc<Mutex<TargetMarkets>>,
+    storage: Storage,
+    mut shutdown: ShutdownReceiver,
+    metrics: Arc<Metrics>,
+    events: EventSender,
+    last_status: Arc<Mutex<Option<StatusEnvelope>>>,
+    bet_sizing: BetSizing,
 ) -> Result<()> {
-    let mut contract_exclusion_list: HashSet<String> = HashSet::new();
+    let mut contract_exclusion_list: HashSet<String> =
+        storage.rehydrate_exclusion_set().await?;
+    info!(
+        count = contract_exclusion_list.len(),
+        "rehydrated exclusion list from storage"
+    );
 
     loop {
+        if shutdown.try_recv().is_ok() {
+            info!("shutdown in progress, not entering a new scan iteration");
+            return Ok(());
+        }
+
         let now = Utc::now().date_naive();
 
         if DATE_EXCLUSION_LIST.contains(&now) {
@@ -67,19 +100,36 @@ async fn scan_targets(
                 "today is on the exclusion list, sleeping for {} minutes",
                 EXCLUSION_DAY_SLEEP_MINUTES
             );
-            sleep(Duration::from_secs(60 * EXCLUSION_DAY_SLEEP_MINUTES)).await;
+            select! {
+                _ = sleep(Duration::from_secs(60 * EXCLUSION_DAY_SLEEP_MINUTES)) => {},
+                _ = shutdown.recv() => return Ok(()),
+            }
             continue;
         }
 
-        let response = github_status::get_incident_status(github_client).await?;
+        let response = select! {
+            response = github_status::get_incident_status(github_client, &metrics) => response?,
+            _ = shutdown.recv() => return Ok(()),
+        };
+
+        *last_status.lock().await = Some(response.clone());
 
         if response.is_ok() {
             debug!("GitHub is working fine, nothing to do, sleeping");
-            sleep(Duration::from_millis(GITHUB_POLL_INTERVAL_MS)).await;
+            select! {
+                _ = sleep(Duration::from_millis(GITHUB_POLL_INTERVAL_MS)) => {},
+                _ = shutdown.recv() => return Ok(()),
+            }
             continue;
         }
 
         let current_incident_type: IncidentType = response.indicator().parse()?;
+        metrics.incidents_observed_total.increment();
+        // Best-effort: no subscribers is not an error.
+        let _ = events.send(BotEvent::IncidentDetected {
+            indicator: current_incident_type.to_string(),
+            description: response.description().to_string(),
+        });
 
         debug!(
             indicator = %current_incident_type,
@@ -108,39 +158,96 @@ async fn scan_targets(
             count = matching_targets.len(),
             "have matching targets not on exclusion list"
         );
+        metrics
+            .targets_matched_total
+            .add(matching_targets.len() as u64);
+
+        let mut balance = manifold_client.get_balance().await?;
+        debug!(balance, "fetched current mana balance");
 
         let mut tasks = Vec::new();
+        let mut amount_by_contract: HashMap<String, u32> = HashMap::new();
 
-        for target in &matching_targets {
+        'queue_bets: for target in &matching_targets {
             debug!(
                 incident_type = %current_incident_type,
-                today_month = now.month(),
-                today_day = now.day(),
-                target_month = target.month,
-                target_day = target.day,
+                today = %now,
+                deadline = %target.deadline,
                 "target matches incident, queuing bet",
             );
 
-            // Bet three times on each target.
-            // We don't know how much mana we have to spend.
+            // Bet twice on each target, sized as a fraction of our remaining mana.
             for _ in 0..NR_OF_BETS {
-                tasks.push(manifold_client.bet(
-                    &target.contract_id,
-                    &Outcome::Yes,
-                    DEFAULT_BET_SIZE,
-                ));
+                let amount = bet_sizing.amount_for(balance);
+                if amount == 0 || amount > balance {
+                    info!(
+                        balance,
+                        min_bet_size = bet_sizing.min(),
+                        "remaining balance too low, stopping this batch of bets"
+                    );
+                    break 'queue_bets;
+                }
+
+                metrics.bets_queued_total.increment();
+                tasks.push(manifold_client.bet(&target.contract_id, &Outcome::Yes, amount));
+                *amount_by_contract
+                    .entry(target.contract_id.clone())
+                    .or_insert(0) += amount;
+                balance -= amount;
+            }
+        }
+
+        let tasks_queued = tasks.len() as u64;
+        match shutdown::with_drain_timeout(async { try_join_all(tasks).await.map(|_| ()) }).await {
+            Ok(()) => {
+                metrics.bets_placed_total.add(tasks_queued);
+                info!("bets placed");
+                for (contract_id, amount) in &amount_by_contract {
+                    let _ = events.send(BotEvent::BetPlaced {
+                        contract_id: contract_id.clone(),
+                        amount: *amount,
+                    });
+                }
+            }
+            Err(err) => {
+                metrics.bets_failed_total.add(tasks_queued);
+                for contract_id in amount_by_contract.keys() {
+                    let _ = events.send(BotEvent::BetFailed {
+                        contract_id: contract_id.clone(),
+                        error: err.to_string(),
+                    });
+                }
+                return Err(err);
             }
         }
 
-        try_join_all(tasks).await?;
-        info!("bets placed");
+        // Persist every bet we just placed, keyed on (contract_id, incident day) so
+        // re-processing the same incident is idempotent.
+        for (contract_id, amount) in &amount_by_contract {
+            storage
+                .record_bet(BetRecord {
+                    contract_id: contract_id.clone(),
+                    outcome: "YES".to_string(),
+                    amount: *amount,
+                    indicator: current_incident_type.to_string(),
+                    description: response.description().to_string(),
+                    incident_date: now,
+                    placed_at: Utc::now(),
+                })
+                .await?;
+        }
 
-        // Add matching targets to the exclusion list.
-        for target in matching_targets {
-            contract_exclusion_list.insert(target.contract_id.clone());
+        // Add only the targets we actually bet on to the exclusion list — a target
+        // that matched this round but never got a bet queued (the batch ran out of
+        // mana first) should still be eligible once the balance recovers.
+        for contract_id in amount_by_contract.keys() {
+            contract_exclusion_list.insert(contract_id.clone());
         }
 
-        sleep(Duration::from_millis(GITHUB_POLL_INTERVAL_MS)).await;
+        select! {
+            _ = sleep(Duration::from_millis(GITHUB_POLL_INTERVAL_MS)) => {},
+            _ = shutdown.recv() => return Ok(()),
+        }
     }
 }
 
@@ -153,22 +260,98 @@ async fn main() -> Result<()> {
     let github_client = reqwest::Client::new();
     let manifold_client = ManifoldClient::new();
 
-    let targets = Arc::new(Mutex::new(TargetMarkets::new()));
+    let storage = Storage::connect(&StorageConfig::from_env()).await?;
+
+    let (_shutdown_tx, shutdown_rx) = shutdown::install();
+
+    let metrics = metrics::new();
+
+    let metrics_thread = tokio::spawn({
+        let metrics = metrics.clone();
+        let shutdown_rx = shutdown_rx.resubscribe();
+        async move { metrics::serve(metrics, metrics::bind_addr_from_env()?, shutdown_rx).await }
+    });
+
+    let events = events::new_bus();
+
+    let notification_thread = tokio::spawn({
+        let events = events.subscribe();
+        let shutdown_rx = shutdown_rx.resubscribe();
+        async move { notification::run(events, shutdown_rx, NotificationConfig::from_env()).await }
+    });
+
+    let rehydrated_targets = storage.load_targets().await?;
+    info!(
+        count = rehydrated_targets.len(),
+        "rehydrated targets from storage"
+    );
+    let targets = Arc::new(Mutex::new(TargetMarkets::from_records(rehydrated_targets)));
+    let last_status: Arc<Mutex<Option<StatusEnvelope>>> = Arc::new(Mutex::new(None));
+
+    let api_thread = tokio::spawn({
+        let state = ApiState {
+            targets: targets.clone(),
+            storage: storage.clone(),
+            last_status: last_status.clone(),
+        };
+        let shutdown_rx = shutdown_rx.resubscribe();
+        async move { api::serve(state, api::bind_addr_from_env()?, shutdown_rx).await }
+    });
 
     let update_targets_thread = tokio::spawn({
         let manifold_client = manifold_client.clone();
         let targets = targets.clone();
-        async move { manifold_markets::update_targets(&manifold_client, targets).await }
+        let storage = storage.clone();
+        let shutdown_rx = shutdown_rx.resubscribe();
+        let metrics = metrics.clone();
+        async move {
+            manifold_markets::update_targets(
+                &manifold_client,
+                targets,
+                storage,
+                shutdown_rx,
+                metrics,
+            )
+            .await
+        }
     });
 
     let scan_targets_thread = tokio::spawn({
-        async move { scan_targets(&github_client, &manifold_client, targets).await }
+        let storage = storage.clone();
+        async move {
+            scan_targets(
+                &github_client,
+                &manifold_client,
+                targets,
+                storage,
+                shutdown_rx,
+                metrics,
+                events,
+                last_status,
+                BetSizing::from_env(),
+            )
+            .await
+        }
     });
 
-    select!(
-        result = update_targets_thread => result.unwrap(),
-        result = scan_targets_thread => result.unwrap()
-    )?;
+    // Join rather than select over the handles: select! returns as soon as the
+    // first task finishes, and with it so does main(), which drops the tokio
+    // runtime and aborts every task still running — including scan_targets
+    // mid-drain. Waiting on all of them ensures the bet batch actually finishes
+    // draining before the process exits.
+    let (update_targets_result, scan_targets_result, metrics_result, notification_result, api_result) = tokio::join!(
+        update_targets_thread,
+        scan_targets_thread,
+        metrics_thread,
+        notification_thread,
+        api_thread,
+    );
+
+    update_targets_result.unwrap()?;
+    scan_targets_result.unwrap()?;
+    metrics_result.unwrap()?;
+    notification_result.unwrap()?;
+    api_result.unwrap()?;
 
     Ok(())
 }
@@ -7,7 +7,7 @@ use std::{
 };
 
 use anyhow::{anyhow, Result};
-use chrono::{Datelike, Utc};
+use chrono::{NaiveDate, Utc};
 use lazy_static::lazy_static;
 use reqwest::{
     self,
@@ -19,6 +19,10 @@ use serde_json::json;
 use tokio::{sync::Mutex, time::sleep};
 use tracing::{debug, trace};
 
+use crate::metrics::Metrics;
+use crate::schedule;
+use crate::shutdown::ShutdownReceiver;
+use crate::storage::{Storage, TargetRecord};
 use crate::TargetIndicident;
 
 const IBLUE_CREATOR_ID: &str = "HBlWMFF8XkcatdnIfNt0RPoCrXy1";
@@ -34,50 +38,7 @@ lazy_static! {
     static ref MANIFOLD_BET_URL: String = format!("{}{}", MANIFOLD_MARKETS_API, BET_PATH);
 }
 
-enum Month {
-    August,
-    September,
-    October,
-    November,
-    December,
-}
-
-impl From<Month> for u32 {
-    fn from(month: Month) -> Self {
-        match month {
-            Month::August => 8,
-            Month::September => 9,
-            Month::October => 10,
-            Month::November => 11,
-            Month::December => 12,
-        }
-    }
-}
-
-fn month_from_question(question: &str) -> Option<Month> {
-    if question.to_lowercase().contains("august") {
-        Some(Month::August)
-    } else if question.to_lowercase().contains("september") {
-        Some(Month::September)
-    } else if question.to_lowercase().contains("october") {
-        Some(Month::October)
-    } else if question.to_lowercase().contains("november") {
-        Some(Month::November)
-    } else if question.to_lowercase().contains("december") {
-        Some(Month::December)
-    } else {
-        None
-    }
-}
-
-fn day_from_question(question: &str) -> Option<u32> {
-    let re = regex::Regex::new(r"on\s+\w+\s+(\d{1,2})").unwrap();
-    re.captures(question)
-        .and_then(|cap| cap.get(1).map(|m| m.as_str().parse().ok()))
-        .flatten()
-}
-
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub enum IncidentType {
     Any,
     Red,
@@ -90,6 +51,10 @@ impl FromStr for IncidentType {
             "minor" => Ok(IncidentType::Any),
             "major" => Ok(IncidentType::Any),
             "critical" => Ok(IncidentType::Red),
+            // The values `Display` writes out, e.g. to the `targets` table -
+            // round-tripped when rehydrating persisted targets.
+            "any" => Ok(IncidentType::Any),
+            "red" => Ok(IncidentType::Red),
             s => Err(anyhow!("unknown incident type: {}", s)),
         }
     }
@@ -144,6 +109,11 @@ impl Market {
 
 type Markets = Vec<Market>;
 
+#[derive(Debug, Deserialize)]
+struct Me {
+    balance: f64,
+}
+
 #[derive(Debug, Clone)]
 pub struct ManifoldClient {
     base_url: String,
@@ -158,6 +128,24 @@ impl ManifoldClient {
         }
     }
 
+    /// Fetch the authenticated account's current mana balance.
+    pub async fn get_balance(&self) -> Result<u32> {
+        let response = self
+            .client
+            .get(&format!("{}/v0/me", self.base_url))
+            .header(AUTHORIZATION, &*AUTHORIZATION_KEY)
+            .send()
+            .await?;
+
+        match response.error_for_status() {
+            Ok(response) => {
+                let me = response.json::<Me>().await?;
+                Ok(me.balance as u32)
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+
     async fn fetch_markets(&self) -> Result<Markets> {
         let response = self
             .client
@@ -201,6 +189,50 @@ impl ManifoldClient {
     }
 }
 
+const DEFAULT_BET_SIZE_FRACTION: f64 = 0.05;
+const DEFAULT_BET_SIZE_MIN: u32 = 10;
+const DEFAULT_BET_SIZE_MAX: u32 = 500;
+
+/// Sizes bets as a fraction of the account's available mana instead of a
+/// hard-coded constant, so the bot adapts to winnings and losses.
+#[derive(Debug, Clone, Copy)]
+pub struct BetSizing {
+    fraction: f64,
+    min: u32,
+    max: u32,
+}
+
+impl BetSizing {
+    pub fn from_env() -> Self {
+        Self {
+            fraction: std::env::var("BET_SIZE_FRACTION")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_BET_SIZE_FRACTION),
+            min: std::env::var("BET_SIZE_MIN")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_BET_SIZE_MIN),
+            max: std::env::var("BET_SIZE_MAX")
+                .ok()
+                .and_then(|s| s.parse().ok())
+                .unwrap_or(DEFAULT_BET_SIZE_MAX),
+        }
+    }
+
+    /// The minimum mana balance a bet is ever placed for. Callers should stop
+    /// queuing further bets in a batch once the remaining balance drops below this.
+    pub fn min(&self) -> u32 {
+        self.min
+    }
+
+    /// Compute the bet amount for the given available balance, clamped to `[min, max]`.
+    pub fn amount_for(&self, balance: u32) -> u32 {
+        let raw = (balance as f64 * self.fraction) as u32;
+        raw.clamp(self.min, self.max)
+    }
+}
+
 const CHECK_MARKETS_INTERVAL_SECONDS: u64 = 6;
 
 #[derive(Debug)]
@@ -211,6 +243,21 @@ impl TargetMarkets {
         Self(HashMap::new())
     }
 
+    /// Rebuild target state from persisted records, so a restart picks up
+    /// targets a previous process already discovered instead of treating the
+    /// Manifold re-fetch on the next `update_targets` tick as all-new.
+    pub fn from_records(records: Vec<TargetRecord>) -> Self {
+        let mut targets = Self::new();
+        for record in records {
+            targets.add_new_target(TargetIndicident {
+                contract_id: record.contract_id,
+                deadline: record.deadline,
+                incident_type: record.incident_type,
+            });
+        }
+        targets
+    }
+
     fn add_new_target(&mut self, target: TargetIndicident) {
         self.0.insert(target.contract_id.clone(), target);
     }
@@ -219,140 +266,126 @@ impl TargetMarkets {
         self.0.contains_key(contract_id)
     }
 
-    fn clear_old_targets(&mut self) {
-        let today = Utc::now();
-        self.0.retain(|_key, target| {
-            let TargetIndicident { month, day, .. } = target;
-            // Keep targets that are for future months, or future days of the current month.
-            *month > today.month() || (*month == today.month() && *day >= today.day())
-        });
+    /// Drop targets whose deadline has passed, returning how many were cleared.
+    fn clear_old_targets(&mut self) -> usize {
+        let before = self.0.len();
+        self.0.retain(|_key, target| !target.is_past());
+        before - self.0.len()
     }
 
     pub fn targets(&self) -> Values<String, TargetIndicident> {
         self.0.values()
     }
+
+    /// A cloned snapshot of the current targets, for the read-only introspection API.
+    pub fn snapshot(&self) -> Vec<TargetIndicident> {
+        self.0.values().cloned().collect()
+    }
+
+    /// The targets whose deadline falls on `now` and whose incident type matches.
+    pub fn matching_targets(
+        &self,
+        now: &NaiveDate,
+        incident_type: &IncidentType,
+    ) -> Vec<&TargetIndicident> {
+        self.0
+            .values()
+            .filter(|target| target.matches(now, incident_type))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 pub async fn update_targets(
     manifold_client: &ManifoldClient,
     target_markets: Arc<Mutex<TargetMarkets>>,
+    storage: Storage,
+    mut shutdown: ShutdownReceiver,
+    metrics: Arc<Metrics>,
 ) -> Result<()> {
     loop {
+        if shutdown.try_recv().is_ok() {
+            debug!("shutdown in progress, not entering a new update iteration");
+            return Ok(());
+        }
+
         debug!("checking for new targets");
 
         for target in target_markets.lock().await.targets() {
             debug!(?target, "current target");
         }
 
-        let markets = manifold_client.fetch_markets().await?;
+        let markets = tokio::select! {
+            markets = manifold_client.fetch_markets() => markets?,
+            _ = shutdown.recv() => return Ok(()),
+        };
+        metrics.markets_fetched_total.add(markets.len() as u64);
 
-        target_markets.lock().await.clear_old_targets();
+        let cleared = target_markets.lock().await.clear_old_targets();
+        metrics.targets_cleared_total.add(cleared as u64);
 
         for market in markets {
-            if market.is_any_incident_market() {
-                let target = TargetIndicident {
-                    contract_id: market.id,
-                    day: day_from_question(&market.question)
-                        .expect("failed to parse day from question"),
-                    incident_type: IncidentType::Any,
-                    month: month_from_question(&market.question)
-                        .expect("failed to parse month from question")
-                        .into(),
-                };
-
-                if target.is_past() {
-                    trace!(?target, "found past target, skipping");
-                    continue;
-                }
-
-                if target_markets
-                    .lock()
-                    .await
-                    .target_exists(&target.contract_id)
-                {
-                    continue;
-                }
-
-                debug!(?target, "found new any incident target");
-                target_markets.lock().await.add_new_target(target);
-
-                // TODO: get the current bets for the market, if you haven't already taken a NO
-                // position, take a NO position.
+            let incident_type = if market.is_any_incident_market() {
+                IncidentType::Any
             } else if market.is_red_incident_market() {
-                let target = TargetIndicident {
-                    contract_id: market.id,
-                    day: day_from_question(&market.question)
-                        .expect("failed to parse day from question"),
-                    incident_type: IncidentType::Red,
-                    month: month_from_question(&market.question)
-                        .expect("failed to parse month from question")
-                        .into(),
-                };
-
-                if target.is_past() {
-                    trace!(?target, "found past target, skipping");
-                    continue;
-                }
-
-                if target_markets
-                    .lock()
-                    .await
-                    .target_exists(&target.contract_id)
-                {
-                    continue;
-                }
-
-                debug!(?target, "found new red incident target");
-                target_markets.lock().await.add_new_target(target);
-
-                // TODO: get the current bets for the market, if you haven't already taken a NO
-                // position, take a NO position.
+                IncidentType::Red
+            } else {
+                continue;
+            };
+
+            let Some(deadline) = schedule::parse_deadline(&market.question, Utc::now()) else {
+                trace!(question = %market.question, "failed to parse a deadline, skipping");
+                continue;
+            };
+
+            let target = TargetIndicident {
+                contract_id: market.id,
+                deadline,
+                incident_type,
+            };
+
+            if target.is_past() {
+                trace!(?target, "found past target, skipping");
+                continue;
+            }
+
+            if target_markets
+                .lock()
+                .await
+                .target_exists(&target.contract_id)
+            {
+                continue;
             }
+
+            debug!(?target, "found new target");
+            storage
+                .save_target(TargetRecord {
+                    contract_id: target.contract_id.clone(),
+                    incident_type: target.incident_type.clone(),
+                    deadline: target.deadline,
+                })
+                .await?;
+            target_markets.lock().await.add_new_target(target);
+            metrics.targets_added_total.increment();
+
+            // TODO: get the current bets for the market, if you haven't already taken a NO
+            // position, take a NO position.
         }
 
-        sleep(Duration::from_secs(CHECK_MARKETS_INTERVAL_SECONDS)).await;
-    }
-}
+        metrics
+            .live_targets
+            .set(target_markets.lock().await.targets().count() as u64);
 
-#[cfg(test)]
-mod tests {
-    use super::day_from_question;
-
-    #[test]
-    fn test_day_from_question() {
-        // Test original examples
-        assert_eq!(
-            day_from_question("Will GitHub have any incident on August 30th 2023?"),
-            Some(30)
-        );
-        assert_eq!(
-            day_from_question("Will GitHub have a red incident on August 30th 2023?"),
-            Some(30)
-        );
-
-        // Test ordinal suffixes
-        assert_eq!(
-            day_from_question("Will GitHub have any incident on August 1st 2023?"),
-            Some(1)
-        );
-        assert_eq!(
-            day_from_question("Will GitHub have any incident on August 01st 2023?"),
-            Some(1)
-        );
-
-        // Test empty case
-        assert_eq!(day_from_question("Will GitHub have any incident?"), None);
-
-        // Test without the word "on"
-        assert_eq!(
-            day_from_question("Will GitHub have any incident August 30th 2023?"),
-            None
-        );
-
-        // Test multiple occurrences of "on"
-        assert_eq!(
-            day_from_question("Will GitHub have any incident on on August 30th 2023?"),
-            Some(30)
-        );
+        tokio::select! {
+            _ = sleep(Duration::from_secs(CHECK_MARKETS_INTERVAL_SECONDS)) => {},
+            _ = shutdown.recv() => return Ok(()),
+        }
     }
 }
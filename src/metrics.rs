@@ -0,0 +1,214 @@
+//! Prometheus metrics, served over HTTP in text exposition format.
+//!
+//! A registry of atomically-incremented named counters and gauges, cloned
+//! into both loops as an `Arc<Metrics>`. Each [`MetricU64`] is backed by an
+//! `AtomicU64`, so it can be updated from multiple tasks through that `Arc`.
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tracing::info;
+use warp::Filter;
+
+use crate::shutdown::ShutdownReceiver;
+
+/// Whether a [`MetricU64`] is monotonically increasing (a Prometheus counter)
+/// or can go up and down (a gauge). Determines the `# TYPE` line emitted.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MetricKind {
+    Counter,
+    Gauge,
+}
+
+impl MetricKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            MetricKind::Counter => "counter",
+            MetricKind::Gauge => "gauge",
+        }
+    }
+}
+
+/// A single named counter or gauge.
+#[derive(Debug)]
+pub struct MetricU64 {
+    name: &'static str,
+    help: &'static str,
+    kind: MetricKind,
+    value: AtomicU64,
+}
+
+impl MetricU64 {
+    /// A monotonically increasing counter. By Prometheus convention its name
+    /// should end in `_total`.
+    fn counter(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: MetricKind::Counter,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    /// A value that can go up and down.
+    fn gauge(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            kind: MetricKind::Gauge,
+            value: AtomicU64::new(0),
+        }
+    }
+
+    pub fn increment(&self) {
+        self.value.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn add(&self, delta: u64) {
+        self.value.fetch_add(delta, Ordering::Relaxed);
+    }
+
+    pub fn set(&self, value: u64) {
+        self.value.store(value, Ordering::Relaxed);
+    }
+
+    pub fn get(&self) -> u64 {
+        self.value.load(Ordering::Relaxed)
+    }
+
+    fn encode(&self) -> String {
+        format!(
+            "# HELP {name} {help}\n# TYPE {name} {kind}\n{name} {value}\n",
+            name = self.name,
+            help = self.help,
+            kind = self.kind.as_str(),
+            value = self.get()
+        )
+    }
+}
+
+/// All metrics the bot exposes, grouped by the subsystem that owns them.
+#[derive(Debug)]
+pub struct Metrics {
+    pub github_polls_total: MetricU64,
+    pub github_poll_retries_total: MetricU64,
+    pub github_poll_latency_ms: MetricU64,
+
+    pub incidents_observed_total: MetricU64,
+    pub targets_matched_total: MetricU64,
+    pub bets_queued_total: MetricU64,
+    pub bets_placed_total: MetricU64,
+    pub bets_failed_total: MetricU64,
+
+    pub markets_fetched_total: MetricU64,
+    pub targets_added_total: MetricU64,
+    pub targets_cleared_total: MetricU64,
+    pub live_targets: MetricU64,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        Self {
+            github_polls_total: MetricU64::counter(
+                "calabi_github_polls_total",
+                "total number of GitHub status polls performed",
+            ),
+            github_poll_retries_total: MetricU64::counter(
+                "calabi_github_poll_retries_total",
+                "total number of GitHub status polls retried after a 429",
+            ),
+            github_poll_latency_ms: MetricU64::gauge(
+                "calabi_github_poll_latency_ms",
+                "latency in milliseconds of the most recent GitHub status poll",
+            ),
+            incidents_observed_total: MetricU64::counter(
+                "calabi_incidents_observed_total",
+                "total number of GitHub status polls that observed an incident, not broken down by indicator",
+            ),
+            targets_matched_total: MetricU64::counter(
+                "calabi_targets_matched_total",
+                "total number of targets matching an observed incident",
+            ),
+            bets_queued_total: MetricU64::counter(
+                "calabi_bets_queued_total",
+                "total number of bets queued for placement",
+            ),
+            bets_placed_total: MetricU64::counter(
+                "calabi_bets_placed_total",
+                "total number of bets successfully placed",
+            ),
+            bets_failed_total: MetricU64::counter(
+                "calabi_bets_failed_total",
+                "total number of bets that failed to place",
+            ),
+            markets_fetched_total: MetricU64::counter(
+                "calabi_markets_fetched_total",
+                "total number of markets fetched from Manifold",
+            ),
+            targets_added_total: MetricU64::counter(
+                "calabi_targets_added_total",
+                "total number of new targets added",
+            ),
+            targets_cleared_total: MetricU64::counter(
+                "calabi_targets_cleared_total",
+                "total number of old targets cleared",
+            ),
+            live_targets: MetricU64::gauge(
+                "calabi_live_targets",
+                "current number of live targets being tracked",
+            ),
+        }
+    }
+
+    fn encode_all(&self) -> String {
+        [
+            self.github_polls_total.encode(),
+            self.github_poll_retries_total.encode(),
+            self.github_poll_latency_ms.encode(),
+            self.incidents_observed_total.encode(),
+            self.targets_matched_total.encode(),
+            self.bets_queued_total.encode(),
+            self.bets_placed_total.encode(),
+            self.bets_failed_total.encode(),
+            self.markets_fetched_total.encode(),
+            self.targets_added_total.encode(),
+            self.targets_cleared_total.encode(),
+            self.live_targets.encode(),
+        ]
+        .concat()
+    }
+}
+
+/// Construct the shared metrics registry.
+pub fn new() -> Arc<Metrics> {
+    Arc::new(Metrics::new())
+}
+
+/// Serve the registry on `bind_addr` in Prometheus text exposition format at
+/// `/metrics`, until `shutdown` fires.
+pub async fn serve(
+    metrics: Arc<Metrics>,
+    bind_addr: SocketAddr,
+    mut shutdown: ShutdownReceiver,
+) -> Result<()> {
+    let route = warp::path("metrics").map(move || metrics.encode_all());
+
+    info!(%bind_addr, "serving prometheus metrics");
+
+    let (_, server) = warp::serve(route).bind_with_graceful_shutdown(bind_addr, async move {
+        shutdown.recv().await.ok();
+    });
+    server.await;
+
+    Ok(())
+}
+
+/// Read `METRICS_BIND_ADDR` from the environment, defaulting to `0.0.0.0:9898`.
+pub fn bind_addr_from_env() -> Result<SocketAddr> {
+    std::env::var("METRICS_BIND_ADDR")
+        .unwrap_or_else(|_| "0.0.0.0:9898".to_string())
+        .parse()
+        .context("invalid METRICS_BIND_ADDR")
+}
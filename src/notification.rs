@@ -0,0 +1,86 @@
+//! Forwards [`BotEvent`]s to configurable sinks, so operators can watch the
+//! bot in real time instead of reading logs.
+//!
+//! At minimum this forwards to an outbound webhook (Discord/Slack-compatible
+//! JSON payload) gated behind an env-configured URL.
+
+use anyhow::Result;
+use reqwest::Client;
+use serde_json::json;
+use tracing::{debug, error, warn};
+
+use crate::events::{BotEvent, EventReceiver};
+use crate::shutdown::ShutdownReceiver;
+
+/// Where notifications get forwarded to. `webhook_url` unset means there are
+/// no sinks configured and events are simply dropped.
+pub struct NotificationConfig {
+    webhook_url: Option<String>,
+}
+
+impl NotificationConfig {
+    pub fn from_env() -> Self {
+        Self {
+            webhook_url: std::env::var("NOTIFICATION_WEBHOOK_URL").ok(),
+        }
+    }
+}
+
+/// Subscribe to the event bus and forward every event to the configured
+/// sinks until shutdown is signalled.
+pub async fn run(
+    mut events: EventReceiver,
+    mut shutdown: ShutdownReceiver,
+    config: NotificationConfig,
+) -> Result<()> {
+    let Some(webhook_url) = config.webhook_url else {
+        debug!("no NOTIFICATION_WEBHOOK_URL set, notifications are disabled");
+        return Ok(());
+    };
+
+    let client = Client::new();
+
+    loop {
+        let event = tokio::select! {
+            event = events.recv() => event,
+            _ = shutdown.recv() => return Ok(()),
+        };
+
+        match event {
+            Ok(event) => {
+                if let Err(err) = send_webhook(&client, &webhook_url, &event).await {
+                    error!(%err, "failed to send notification webhook");
+                }
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                warn!(skipped, "notification receiver lagged, some events were dropped");
+            }
+            Err(tokio::sync::broadcast::error::RecvError::Closed) => return Ok(()),
+        }
+    }
+}
+
+async fn send_webhook(client: &Client, webhook_url: &str, event: &BotEvent) -> Result<()> {
+    let content = match event {
+        BotEvent::IncidentDetected {
+            indicator,
+            description,
+        } => format!("🚨 GitHub incident detected ({indicator}): {description}"),
+        BotEvent::BetPlaced {
+            contract_id,
+            amount,
+        } => format!("✅ placed {amount} mana in bets on {contract_id}"),
+        BotEvent::BetFailed { contract_id, error } => {
+            format!("❌ failed to place a batch of bets including {contract_id}: {error}")
+        }
+    };
+
+    client
+        .post(webhook_url)
+        .json(&json!({ "content": content }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Centralized market-schedule parsing.
+//!
+//! Every market question is parsed into a single fully-qualified
+//! `DateTime<Utc>` close deadline (year + month + day + an end-of-day close
+//! time), following the "set position expiry only in one place" approach:
+//! `TargetIndicident::is_past`, `matches`, and `TargetMarkets::clear_old_targets`
+//! all route through this one representation instead of each reconstructing
+//! month/day ad hoc. That ad hoc reconstruction ignored the year entirely,
+//! so a target for a past December could be mistaken for live in January.
+
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, TimeZone, Utc};
+use lazy_static::lazy_static;
+use regex::Regex;
+
+/// Markets close at the end of the day UTC, since the question text doesn't
+/// expose an exact intraday close time.
+const MARKET_CLOSE_HOUR: u32 = 23;
+const MARKET_CLOSE_MINUTE: u32 = 59;
+const MARKET_CLOSE_SECOND: u32 = 59;
+
+lazy_static! {
+    static ref DEADLINE_RE: Regex = Regex::new(
+        r"(?i)on\s+(january|february|march|april|may|june|july|august|september|october|november|december)\s+(\d{1,2})(?:st|nd|rd|th)?(?:,?\s+(\d{4}))?"
+    )
+    .unwrap();
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    match name.to_lowercase().as_str() {
+        "january" => Some(1),
+        "february" => Some(2),
+        "march" => Some(3),
+        "april" => Some(4),
+        "may" => Some(5),
+        "june" => Some(6),
+        "july" => Some(7),
+        "august" => Some(8),
+        "september" => Some(9),
+        "october" => Some(10),
+        "november" => Some(11),
+        "december" => Some(12),
+        _ => None,
+    }
+}
+
+/// Parse a market question into its fully-qualified close deadline.
+///
+/// `now` is threaded in (rather than reading `Utc::now()` internally) so the
+/// year inference below is deterministic and testable.
+pub fn parse_deadline(question: &str, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let captures = DEADLINE_RE.captures(question)?;
+    let month = month_number(&captures[1])?;
+    let day: u32 = captures[2].parse().ok()?;
+    let year = match captures.get(3) {
+        Some(year) => year.as_str().parse().ok()?,
+        None => infer_year(month, day, now)?,
+    };
+
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    let time = NaiveTime::from_hms_opt(MARKET_CLOSE_HOUR, MARKET_CLOSE_MINUTE, MARKET_CLOSE_SECOND)?;
+
+    Some(Utc.from_utc_datetime(&date.and_time(time)))
+}
+
+/// When a question doesn't state a year, pick the year that puts the close
+/// date on or after `now`, so a dateless question phrased near a year
+/// boundary rolls over to next year instead of resolving to an already-past
+/// date.
+fn infer_year(month: u32, day: u32, now: DateTime<Utc>) -> Option<i32> {
+    let candidate = NaiveDate::from_ymd_opt(now.year(), month, day)?;
+    if candidate < now.date_naive() {
+        Some(now.year() + 1)
+    } else {
+        Some(now.year())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_deadline;
+    use chrono::{TimeZone, Utc};
+
+    fn at(year: i32, month: u32, day: u32) -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_an_explicit_year() {
+        let deadline = parse_deadline(
+            "Will GitHub have any incident on August 30th 2023?",
+            at(2023, 1, 1),
+        )
+        .unwrap();
+        assert_eq!(deadline.date_naive(), at(2023, 8, 30).date_naive());
+    }
+
+    #[test]
+    fn parses_ordinal_suffixes() {
+        let deadline = parse_deadline(
+            "Will GitHub have any incident on August 1st 2023?",
+            at(2023, 1, 1),
+        )
+        .unwrap();
+        assert_eq!(deadline.date_naive(), at(2023, 8, 1).date_naive());
+    }
+
+    #[test]
+    fn returns_none_without_a_match() {
+        assert!(parse_deadline("Will GitHub have any incident?", at(2023, 1, 1)).is_none());
+    }
+
+    #[test]
+    fn infers_next_year_once_this_years_date_has_passed() {
+        // No year stated, and March 1st of the current year is already behind `now`.
+        let deadline =
+            parse_deadline("Will GitHub have any incident on March 1st?", at(2026, 6, 1)).unwrap();
+        assert_eq!(deadline.date_naive(), at(2027, 3, 1).date_naive());
+    }
+
+    #[test]
+    fn infers_current_year_when_still_upcoming() {
+        let deadline =
+            parse_deadline("Will GitHub have any incident on December 31st?", at(2026, 6, 1))
+                .unwrap();
+        assert_eq!(deadline.date_naive(), at(2026, 12, 31).date_naive());
+    }
+}
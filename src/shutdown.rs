@@ -0,0 +1,81 @@
+//! Graceful shutdown signalling.
+//!
+//! Installs SIGINT/SIGTERM handlers and fans a single shutdown notice out to
+//! every loop over a `broadcast` channel, so a signal mid-batch stops new work
+//! from being queued without tearing down bets already in flight.
+
+use anyhow::Result;
+use tokio::sync::broadcast;
+use tracing::info;
+
+/// How long a batch of in-flight work is awaited before it is given up on.
+/// The timer starts with the batch and applies whether or not shutdown has
+/// been signalled.
+pub const DRAIN_TIMEOUT_SECS: u64 = 30;
+
+/// Handle held by each loop to observe the shutdown signal.
+pub type ShutdownReceiver = broadcast::Receiver<()>;
+
+/// Install SIGINT/SIGTERM handlers and return a single receiver, which each
+/// loop that needs to observe shutdown gets its own copy of via
+/// `resubscribe()`, plus the sender kept alive for the process lifetime
+/// (dropping it would close the channel early).
+pub fn install() -> (broadcast::Sender<()>, ShutdownReceiver) {
+    let (tx, rx) = broadcast::channel(1);
+
+    tokio::spawn({
+        let tx = tx.clone();
+        async move {
+            wait_for_signal().await;
+            info!("shutdown signal received, draining in-flight work");
+            // Send is best-effort: if every receiver already dropped there's
+            // nothing left to notify.
+            let _ = tx.send(());
+        }
+    });
+
+    (tx, rx)
+}
+
+#[cfg(unix)]
+async fn wait_for_signal() {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut sigint = signal(SignalKind::interrupt()).expect("failed to install SIGINT handler");
+    let mut sigterm = signal(SignalKind::terminate()).expect("failed to install SIGTERM handler");
+
+    tokio::select! {
+        _ = sigint.recv() => info!("received SIGINT"),
+        _ = sigterm.recv() => info!("received SIGTERM"),
+    }
+}
+
+#[cfg(not(unix))]
+async fn wait_for_signal() {
+    let _ = tokio::signal::ctrl_c().await;
+    info!("received ctrl-c");
+}
+
+/// Await `work`, but stop waiting once `DRAIN_TIMEOUT_SECS` has passed so a
+/// stuck batch of bets can't hang the shutdown forever. On timeout the
+/// unfinished work is dropped and `Ok(())` is returned.
+pub async fn with_drain_timeout<F>(work: F) -> Result<()>
+where
+    F: std::future::Future<Output = Result<()>>,
+{
+    match tokio::time::timeout(
+        std::time::Duration::from_secs(DRAIN_TIMEOUT_SECS),
+        work,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(_) => {
+            info!(
+                timeout_secs = DRAIN_TIMEOUT_SECS,
+                "drain timeout elapsed, dropping in-flight work, which may be incomplete"
+            );
+            Ok(())
+        }
+    }
+}
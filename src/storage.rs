@@ -0,0 +1,319 @@
+//! Persistence for placed bets and discovered targets.
+//!
+//! Backed by Postgres when `DATABASE_URL` is set in the environment, falling
+//! back to a pure in-memory mode otherwise so the bot still runs without a
+//! database in dev. A single writer task drains bet records off a channel and
+//! inserts them, so recording a bet doesn't wait on the insert. Reads and
+//! target writes use the shared Postgres client directly.
+
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use native_tls::TlsConnector;
+use postgres_native_tls::MakeTlsConnector;
+use serde::Serialize;
+use tokio::sync::mpsc;
+use tokio_postgres::NoTls;
+use tracing::{debug, error, info};
+
+use crate::manifold_markets::IncidentType;
+
+const BET_CHANNEL_CAPACITY: usize = 256;
+const RECENT_BETS_LIMIT: i64 = 50;
+
+/// The bets placed by [`crate::manifold_markets::ManifoldClient::bet`] on one
+/// contract for one incident day, with `amount` their total, ready to be persisted.
+#[derive(Debug, Clone, Serialize)]
+pub struct BetRecord {
+    pub contract_id: String,
+    pub outcome: String,
+    pub amount: u32,
+    pub indicator: String,
+    pub description: String,
+    pub incident_date: NaiveDate,
+    pub placed_at: DateTime<Utc>,
+}
+
+/// A discovered target market, ready to be persisted.
+#[derive(Debug, Clone)]
+pub struct TargetRecord {
+    pub contract_id: String,
+    pub incident_type: IncidentType,
+    /// The market's fully-qualified close deadline, per [`crate::schedule`].
+    pub deadline: DateTime<Utc>,
+}
+
+/// Connection config, read from the environment. `DATABASE_URL` unset means
+/// run in-memory with no persistence across restarts.
+pub struct StorageConfig {
+    database_url: Option<String>,
+    ssl: bool,
+}
+
+impl StorageConfig {
+    pub fn from_env() -> Self {
+        Self {
+            database_url: std::env::var("DATABASE_URL").ok(),
+            ssl: std::env::var("DATABASE_SSL")
+                .map(|s| s == "true")
+                .unwrap_or(false),
+        }
+    }
+}
+
+/// Handle to the persistence layer. Cheap to clone, sharing the underlying
+/// Postgres client and bet-writer channel.
+#[derive(Clone)]
+pub enum Storage {
+    Postgres {
+        client: std::sync::Arc<tokio_postgres::Client>,
+        bet_tx: mpsc::Sender<BetRecord>,
+    },
+    Memory,
+}
+
+impl Storage {
+    /// Connect to Postgres per `config`, running migrations and starting the
+    /// bet-writer task. Falls back to [`Storage::Memory`] when no
+    /// `DATABASE_URL` is configured.
+    pub async fn connect(config: &StorageConfig) -> Result<Self> {
+        let Some(database_url) = &config.database_url else {
+            info!("no DATABASE_URL set, running with in-memory storage");
+            return Ok(Storage::Memory);
+        };
+
+        let client = if config.ssl {
+            let connector = TlsConnector::builder()
+                .build()
+                .context("failed to build TLS connector")?;
+            let connector = MakeTlsConnector::new(connector);
+            let (client, connection) = tokio_postgres::connect(database_url, connector)
+                .await
+                .context("failed to connect to postgres over TLS")?;
+
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    error!(%err, "postgres connection closed with an error");
+                }
+            });
+
+            client
+        } else {
+            let (client, connection) = tokio_postgres::connect(database_url, NoTls)
+                .await
+                .context("failed to connect to postgres")?;
+
+            tokio::spawn(async move {
+                if let Err(err) = connection.await {
+                    error!(%err, "postgres connection closed with an error");
+                }
+            });
+
+            client
+        };
+
+        run_migrations(&client).await?;
+
+        let client = std::sync::Arc::new(client);
+        let bet_tx = spawn_bet_writer(client.clone());
+
+        info!("connected to postgres storage");
+
+        Ok(Storage::Postgres { client, bet_tx })
+    }
+
+    /// Rehydrate the set of contract ids we've already bet on, so a restart
+    /// doesn't re-bet markets it already hit.
+    pub async fn rehydrate_exclusion_set(&self) -> Result<HashSet<String>> {
+        match self {
+            Storage::Memory => Ok(HashSet::new()),
+            Storage::Postgres { client, .. } => {
+                let rows = client
+                    .query("SELECT DISTINCT contract_id FROM bets", &[])
+                    .await
+                    .context("failed to rehydrate exclusion set")?;
+
+                Ok(rows.into_iter().map(|row| row.get(0)).collect())
+            }
+        }
+    }
+
+    /// Queue a bet for persistence. Idempotent on (contract_id, incident_date).
+    pub async fn record_bet(&self, record: BetRecord) -> Result<()> {
+        match self {
+            Storage::Memory => {
+                debug!(?record, "in-memory storage, not persisting bet");
+                Ok(())
+            }
+            Storage::Postgres { bet_tx, .. } => bet_tx
+                .send(record)
+                .await
+                .context("bet writer task has shut down"),
+        }
+    }
+
+    /// Fetch the most recently placed bets, for the read-only introspection API.
+    pub async fn recent_bets(&self) -> Result<Vec<BetRecord>> {
+        match self {
+            Storage::Memory => Ok(Vec::new()),
+            Storage::Postgres { client, .. } => {
+                let rows = client
+                    .query(
+                        "SELECT contract_id, outcome, amount, indicator, description, \
+                         incident_date, placed_at FROM bets ORDER BY placed_at DESC LIMIT $1",
+                        &[&RECENT_BETS_LIMIT],
+                    )
+                    .await
+                    .context("failed to fetch recent bets")?;
+
+                Ok(rows
+                    .into_iter()
+                    .map(|row| BetRecord {
+                        contract_id: row.get(0),
+                        outcome: row.get(1),
+                        amount: row.get::<_, i32>(2) as u32,
+                        indicator: row.get(3),
+                        description: row.get(4),
+                        incident_date: row.get(5),
+                        placed_at: row.get(6),
+                    })
+                    .collect())
+            }
+        }
+    }
+
+    /// Load every persisted target, for rehydrating [`crate::manifold_markets::TargetMarkets`] on startup.
+    pub async fn load_targets(&self) -> Result<Vec<TargetRecord>> {
+        match self {
+            Storage::Memory => Ok(Vec::new()),
+            Storage::Postgres { client, .. } => {
+                let rows = client
+                    .query("SELECT contract_id, incident_type, deadline FROM targets", &[])
+                    .await
+                    .context("failed to load targets")?;
+
+                rows.into_iter()
+                    .map(|row| {
+                        let incident_type: String = row.get(1);
+                        Ok(TargetRecord {
+                            contract_id: row.get(0),
+                            incident_type: incident_type
+                                .parse()
+                                .context("stored target has an unrecognized incident_type")?,
+                            deadline: row.get(2),
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Persist a newly discovered target, upserting so re-discovery is a no-op.
+    pub async fn save_target(&self, target: TargetRecord) -> Result<()> {
+        match self {
+            Storage::Memory => {
+                debug!(?target, "in-memory storage, not persisting target");
+                Ok(())
+            }
+            Storage::Postgres { client, .. } => {
+                client
+                    .execute(
+                        "INSERT INTO targets (contract_id, incident_type, deadline) \
+                         VALUES ($1, $2, $3) \
+                         ON CONFLICT (contract_id) DO NOTHING",
+                        &[
+                            &target.contract_id,
+                            &target.incident_type.to_string(),
+                            &target.deadline,
+                        ],
+                    )
+                    .await
+                    .context("failed to save target")?;
+                Ok(())
+            }
+        }
+    }
+}
+
+async fn run_migrations(client: &tokio_postgres::Client) -> Result<()> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS bets (
+                id BIGSERIAL PRIMARY KEY,
+                contract_id TEXT NOT NULL,
+                outcome TEXT NOT NULL,
+                amount INTEGER NOT NULL,
+                indicator TEXT NOT NULL,
+                description TEXT NOT NULL,
+                incident_date DATE NOT NULL,
+                placed_at TIMESTAMPTZ NOT NULL,
+                UNIQUE (contract_id, incident_date)
+            );
+            CREATE TABLE IF NOT EXISTS targets (
+                contract_id TEXT PRIMARY KEY,
+                incident_type TEXT NOT NULL
+            );
+            -- `deadline` replaced the old `month`/`day` columns when target close
+            -- times were unified into a single DateTime<Utc>; add it and backfill
+            -- existing rows in place instead of rewriting CREATE TABLE, so a
+            -- deployment that already ran the old migration doesn't get stuck with
+            -- `deadline` missing. Guarded on `month`/`day` actually existing, since
+            -- a brand-new database never had them and CREATE TABLE above no longer
+            -- creates them either.
+            ALTER TABLE targets ADD COLUMN IF NOT EXISTS deadline TIMESTAMPTZ;
+            DO $$
+            BEGIN
+                IF EXISTS (
+                    SELECT 1 FROM information_schema.columns
+                    WHERE table_name = 'targets' AND column_name = 'month'
+                ) THEN
+                    UPDATE targets SET deadline = make_timestamptz(
+                        EXTRACT(YEAR FROM now())::INT, month, day, 23, 59, 59, 'UTC'
+                    ) WHERE deadline IS NULL AND month IS NOT NULL AND day IS NOT NULL;
+                    ALTER TABLE targets DROP COLUMN month;
+                    ALTER TABLE targets DROP COLUMN day;
+                END IF;
+            END $$;
+            ALTER TABLE targets ALTER COLUMN deadline SET NOT NULL;",
+        )
+        .await
+        .context("failed to run storage migrations")
+}
+
+/// Spawn the single task that owns writing bets to Postgres, draining the
+/// channel sequentially so duplicate emissions (e.g. the `NR_OF_BETS` loop,
+/// or a retried incident) upsert instead of creating phantom rows.
+fn spawn_bet_writer(client: std::sync::Arc<tokio_postgres::Client>) -> mpsc::Sender<BetRecord> {
+    let (tx, mut rx) = mpsc::channel::<BetRecord>(BET_CHANNEL_CAPACITY);
+
+    tokio::spawn(async move {
+        while let Some(record) = rx.recv().await {
+            let result = client
+                .execute(
+                    "INSERT INTO bets \
+                     (contract_id, outcome, amount, indicator, description, incident_date, placed_at) \
+                     VALUES ($1, $2, $3, $4, $5, $6, $7) \
+                     ON CONFLICT (contract_id, incident_date) DO NOTHING",
+                    &[
+                        &record.contract_id,
+                        &record.outcome,
+                        &(record.amount as i32),
+                        &record.indicator,
+                        &record.description,
+                        &record.incident_date,
+                        &record.placed_at,
+                    ],
+                )
+                .await;
+
+            if let Err(err) = result {
+                error!(%err, contract_id = %record.contract_id, "failed to persist bet");
+            }
+        }
+
+        debug!("bet writer task shutting down, sender dropped");
+    });
+
+    tx
+}